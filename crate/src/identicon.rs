@@ -0,0 +1,140 @@
+//! Deterministic identicon/avatar generation from an arbitrary seed string.
+//!
+//! The same seed always produces the same image, which makes this useful for
+//! default profile pictures generated entirely in WASM, without a round trip
+//! to a server.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{PhotonImage, Rgb};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Hash a seed string with FNV-1a. Used to deterministically derive both the
+/// foreground colour and the identicon's pixel grid.
+fn fnv1a(seed: &str) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in seed.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Generate a deterministic identicon for `seed`.
+///
+/// `grid` is the size of the symmetric boolean grid (e.g. `5` for a 5x5
+/// identicon), and `size` is the resulting image's pixel width/height, with
+/// each grid cell nearest-neighbor upscaled to fill it.
+#[wasm_bindgen]
+pub fn generate_identicon(seed: &str, grid: u32, size: u32) -> PhotonImage {
+    let hash = fnv1a(seed);
+    let hash_bytes = hash.to_be_bytes();
+
+    let foreground = foreground_colour(hash_bytes[0], hash_bytes[1]);
+    let background = Rgb::new(240, 240, 240);
+
+    let grid = grid.max(1);
+    let cells = build_grid(seed, grid);
+
+    render_grid(&cells, grid, size, &foreground, &background)
+}
+
+/// Derive a foreground colour from hash bytes, guaranteed to contrast
+/// against the fixed light `background`.
+///
+/// Taking hash bytes verbatim as RGB (as a naive implementation would) lets
+/// a seed whose bytes happen to land near the background colour produce a
+/// near-invisible identicon. Deriving hue and saturation from the hash but
+/// clamping lightness to a fixed, mid-to-dark range instead guarantees a
+/// minimum contrast against the background for every seed, while keeping
+/// the colour itself fully seed-dependent.
+fn foreground_colour(hue_byte: u8, saturation_byte: u8) -> Rgb {
+    let hue = hue_byte as f32 / 255.0 * 360.0;
+    let saturation = 0.45 + (saturation_byte as f32 / 255.0) * 0.25;
+    let lightness = 0.4;
+
+    hsl_to_rgb(hue, saturation, lightness)
+}
+
+/// Convert an HSL colour (hue in degrees, saturation/lightness in `0.0-1.0`)
+/// to 8-bit RGB.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> Rgb {
+    let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h_prime = hue / 60.0;
+    let x = c * (1.0 - (h_prime % 2.0 - 1.0).abs());
+    let m = lightness - c / 2.0;
+
+    let (r, g, b) = if h_prime < 1.0 {
+        (c, x, 0.0)
+    } else if h_prime < 2.0 {
+        (x, c, 0.0)
+    } else if h_prime < 3.0 {
+        (0.0, c, x)
+    } else if h_prime < 4.0 {
+        (0.0, x, c)
+    } else if h_prime < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    Rgb::new(
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+    )
+}
+
+/// Build a `grid x grid` boolean matrix: a cell in the left half (plus the
+/// center column, for odd grid sizes) is "on" if the corresponding hash bit
+/// is set, then mirrored horizontally so the identicon is symmetric.
+///
+/// A single FNV-1a hash only supplies 64 bits, which covers grids up to
+/// `10x10` (`grid * ((grid + 1) / 2)` bits needed). Past that, additional
+/// 64-bit words are derived by re-hashing the seed salted with a word index,
+/// so larger grids keep gaining fresh entropy instead of repeating the first
+/// 64 bits in a cycle.
+fn build_grid(seed: &str, grid: u32) -> Vec<bool> {
+    let grid = grid as usize;
+    let half = (grid + 1) / 2;
+    let mut cells = vec![false; grid * grid];
+
+    let mut word = fnv1a(seed);
+    let mut word_index = 0u64;
+    let mut bit_index = 0usize;
+    for y in 0..grid {
+        for x in 0..half {
+            if bit_index > 0 && bit_index % 64 == 0 {
+                word_index += 1;
+                word = fnv1a(&format!("{}:{}", seed, word_index));
+            }
+            let bit = (word >> (bit_index % 64)) & 1 == 1;
+            cells[y * grid + x] = bit;
+            cells[y * grid + (grid - 1 - x)] = bit;
+            bit_index += 1;
+        }
+    }
+
+    cells
+}
+
+/// Nearest-neighbor upscale the boolean grid into a `size x size` PhotonImage.
+fn render_grid(cells: &[bool], grid: u32, size: u32, foreground: &Rgb, background: &Rgb) -> PhotonImage {
+    let mut raw_pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        let grid_y = (y as u64 * grid as u64 / size as u64) as u32;
+        for x in 0..size {
+            let grid_x = (x as u64 * grid as u64 / size as u64) as u32;
+            let colour = if cells[(grid_y * grid + grid_x) as usize] {
+                foreground
+            } else {
+                background
+            };
+            raw_pixels.extend_from_slice(&[colour.r, colour.g, colour.b, 255]);
+        }
+    }
+
+    PhotonImage::new(raw_pixels, size, size)
+}
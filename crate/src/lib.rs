@@ -58,13 +58,65 @@
 //! View the [official demo of WASM in action](https://silvia-odwyer.github.io/photon).
 //! Not all functions available in the core Rust library are available in WebAssembly (currently investigating this). Only WASM-friendly functions have been annotated with #[wasm_bindgen]. All supported WASM functions are displayed in the starter demo. 
 
+use std::fmt;
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, ImageData, HtmlCanvasElement};
 use wasm_bindgen::Clamped;
 use image::{GenericImage, GenericImageView};
-use base64::decode;
+use base64::{decode, encode};
 use serde::{Serialize, Deserialize};
 
+/// The error type returned by fallible Photon operations, such as decoding
+/// a base64 string or an unsupported byte slice into a `PhotonImage`.
+#[derive(Debug)]
+pub enum PhotonError {
+    /// The image bytes could not be decoded, or `to_bytes` could not encode
+    /// the pixels.
+    Decode(String),
+    /// The image format is not supported by the underlying image decoder.
+    UnsupportedFormat(String),
+    /// The supplied width/height do not match the pixel buffer.
+    InvalidDimensions(String),
+    /// The base64 string could not be decoded.
+    Base64(base64::DecodeError),
+}
+
+impl fmt::Display for PhotonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhotonError::Decode(msg) => write!(f, "failed to decode or encode image: {}", msg),
+            PhotonError::UnsupportedFormat(msg) => write!(f, "unsupported image format: {}", msg),
+            PhotonError::InvalidDimensions(msg) => write!(f, "invalid image dimensions: {}", msg),
+            PhotonError::Base64(err) => write!(f, "failed to decode base64 string: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PhotonError {}
+
+impl From<image::ImageError> for PhotonError {
+    fn from(err: image::ImageError) -> Self {
+        match err {
+            image::ImageError::UnsupportedError(msg) => PhotonError::UnsupportedFormat(msg),
+            err => PhotonError::Decode(err.to_string()),
+        }
+    }
+}
+
+impl From<base64::DecodeError> for PhotonError {
+    fn from(err: base64::DecodeError) -> Self {
+        PhotonError::Base64(err)
+    }
+}
+
+/// Allows a `PhotonError` to be thrown as a JS exception from any
+/// `#[wasm_bindgen]` function returning `Result<_, PhotonError>`.
+impl From<PhotonError> for JsValue {
+    fn from(err: PhotonError) -> Self {
+        JsValue::from_str(&err.to_string())
+    }
+}
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 // #[cfg(feature = "wee_alloc")]
@@ -72,78 +124,209 @@ use serde::{Serialize, Deserialize};
 // static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 /// Provides the image's height, width, and contains the image's raw pixels.
-/// For use when communicating between JS and WASM, and also natively. 
-#[wasm_bindgen]
-#[derive(Serialize, Deserialize, Debug)]
-pub struct PhotonImage {
-    raw_pixels: Vec<u8>,
-    width: u32, 
-    height: u32,
-}
-
-#[wasm_bindgen]
-impl PhotonImage {   
-    #[wasm_bindgen(constructor)]
-    pub fn new(raw_pixels: Vec<u8>, width: u32, height: u32) -> PhotonImage {
-        return PhotonImage { raw_pixels: raw_pixels, width: width, height: height};
+/// For use when communicating between JS and WASM, and also natively.
+///
+/// `raw_pixels` lives behind this private submodule (rather than directly in
+/// the crate root) so that sibling modules (`effects`, `filters`,
+/// `channels`, ...) cannot reach it as a bare field and silently bypass the
+/// `data_url_cache` invalidation below; they must go through `raw_pixels()`
+/// / `raw_pixels_mut()` instead.
+mod photon_image {
+    use std::cell::RefCell;
+
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::Clamped;
+    use web_sys::ImageData;
+    use image::{GenericImage, GenericImageView};
+    use serde::{Serialize, Deserialize};
+
+    use crate::{encode, to_raw_pixels, ImageFormat, PhotonError};
+
+    #[wasm_bindgen]
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct PhotonImage {
+        raw_pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+        /// Lazily-computed, cached base64 `data:` URL for this image's current
+        /// pixels. Invalidated whenever `raw_pixels` is replaced.
+        #[serde(skip)]
+        data_url_cache: RefCell<Option<String>>,
     }
 
-    /// Create a new PhotonImage from a base64 string.
-    pub fn new_from_base64(base64: &str) -> PhotonImage {
-        let image = base64_to_image(base64);
-        return image;
-    }
+    #[wasm_bindgen]
+    impl PhotonImage {
+        #[wasm_bindgen(constructor)]
+        pub fn new(raw_pixels: Vec<u8>, width: u32, height: u32) -> PhotonImage {
+            return PhotonImage { raw_pixels: raw_pixels, width: width, height: height, data_url_cache: RefCell::new(None)};
+        }
 
-    pub fn new_from_byteslice(vec: Vec<u8>) -> PhotonImage {    
-        let slice = vec.as_slice();
+        /// Create a new PhotonImage from a base64 string.
+        pub fn new_from_base64(base64: &str) -> Result<PhotonImage, PhotonError> {
+            crate::base64_to_image(base64)
+        }
 
-        let img = image::load_from_memory(slice).unwrap();
-        
-        let raw_pixels = img.raw_pixels();
-        
-        return PhotonImage { raw_pixels: raw_pixels, width: img.width(), height: img.height()};
-    
-    }
+        pub fn new_from_byteslice(vec: Vec<u8>) -> Result<PhotonImage, PhotonError> {
+            let slice = vec.as_slice();
 
-    /// Get the width of the PhotonImage.
-    pub fn get_width(&self) -> u32 {
-        self.width
-    }
+            let img = image::load_from_memory(slice)?;
 
-    pub fn get_raw_pixels(&self) -> Vec<u8> {
-        self.raw_pixels.clone()
-    }
+            let raw_pixels = img.raw_pixels();
+
+            Ok(PhotonImage { raw_pixels: raw_pixels, width: img.width(), height: img.height(), data_url_cache: RefCell::new(None)})
+        }
+
+        /// Get the width of the PhotonImage.
+        pub fn get_width(&self) -> u32 {
+            self.width
+        }
+
+        pub fn get_raw_pixels(&self) -> Vec<u8> {
+            self.raw_pixels.clone()
+        }
+
+        /// Get the height of the PhotonImage.
+        pub fn get_height(&self) -> u32 {
+            self.height
+        }
+
+        /// Convert the PhotonImage's raw pixels to JS-compatible ImageData.
+        ///
+        /// This only copies pixels out for the web-sys API (which requires a
+        /// mutable slice purely for ABI reasons); it doesn't change them, so
+        /// it must not invalidate the `data:` URL cache.
+        pub fn get_image_data(&mut self) -> Result<ImageData, PhotonError> {
+            let width = self.width;
+            let height = self.height;
+            ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut self.raw_pixels), width, height)
+                .map_err(|err| PhotonError::InvalidDimensions(format!("{:?}", err)))
+        }
+
+        /// Convert ImageData to raw pixels, and update the PhotonImage's raw pixels to this.
+        pub fn set_imgdata(&mut self, img_data: ImageData) {
+            let width = img_data.width();
+            let height = img_data.height();
+            let raw_pixels = to_raw_pixels(img_data);
+            self.width = width;
+            self.height = height;
+            *self.raw_pixels_mut() = raw_pixels;
+        }
+
+        /// Lazily compute and cache this image's base64 PNG `data:` URL.
+        ///
+        /// Repeated calls reuse the cached string until the pixels change (e.g.
+        /// via `set_imgdata`), which avoids redundant PNG encoding when the same
+        /// PhotonImage is redrawn every frame of a canvas/video loop.
+        pub fn as_data_url(&self) -> Result<String, PhotonError> {
+            if let Some(cached) = self.data_url_cache.borrow().as_ref() {
+                return Ok(cached.clone());
+            }
+
+            let data_url = self.to_base64_data_url(ImageFormat::Png, 100)?;
+            *self.data_url_cache.borrow_mut() = Some(data_url.clone());
+            Ok(data_url)
+        }
+
+        /// Encode the PhotonImage's raw pixels into a concrete image format.
+        ///
+        /// `quality` is honoured only by lossy formats (currently JPEG) and should
+        /// be in the range `0-100`.
+        pub fn to_bytes(&self, format: ImageFormat, quality: u8) -> Result<Vec<u8>, PhotonError> {
+            let img_buffer: image::RgbaImage = image::ImageBuffer::from_raw(self.width, self.height, self.raw_pixels.clone())
+                .ok_or_else(|| PhotonError::InvalidDimensions("raw pixels do not match width/height".to_string()))?;
+
+            let mut bytes: Vec<u8> = Vec::new();
+
+            match format {
+                ImageFormat::Png => {
+                    image::png::PNGEncoder::new(&mut bytes)
+                        .encode(&img_buffer, self.width, self.height, image::ColorType::RGBA(8))
+                        .map_err(|err| PhotonError::Decode(err.to_string()))?;
+                }
+                ImageFormat::Jpeg => {
+                    // JPEG has no alpha channel, so flatten onto an RGB buffer first.
+                    let rgb_img = image::DynamicImage::ImageRgba8(img_buffer).to_rgb();
+                    image::jpeg::JPEGEncoder::new_with_quality(&mut bytes, quality)
+                        .encode(&rgb_img, self.width, self.height, image::ColorType::RGB(8))
+                        .map_err(|err| PhotonError::Decode(err.to_string()))?;
+                }
+                ImageFormat::Bmp => {
+                    image::bmp::BMPEncoder::new(&mut bytes)
+                        .encode(&img_buffer, self.width, self.height, image::ColorType::RGBA(8))
+                        .map_err(|err| PhotonError::Decode(err.to_string()))?;
+                }
+            }
+
+            Ok(bytes)
+        }
 
-    /// Get the height of the PhotonImage.
-    pub fn get_height(&self) -> u32 {
-        self.height
+        /// Encode the PhotonImage to `format` and wrap it as a base64 `data:` URL,
+        /// ready to be used directly as an `<img src>` or download link.
+        pub fn to_base64_data_url(&self, format: ImageFormat, quality: u8) -> Result<String, PhotonError> {
+            let bytes = self.to_bytes(format, quality)?;
+            let mime = match format {
+                ImageFormat::Png => "image/png",
+                ImageFormat::Jpeg => "image/jpeg",
+                ImageFormat::Bmp => "image/bmp",
+            };
+            Ok(format!("data:{};base64,{}", mime, encode(&bytes)))
+        }
     }
 
-    /// Convert the PhotonImage's raw pixels to JS-compatible ImageData.
-    pub fn get_image_data(&mut self) -> ImageData {
-        let new_img_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut self.raw_pixels), self.width, self.height).unwrap();
-        new_img_data
+    impl PhotonImage {
+        /// Invalidate the cached `data:` URL. Any function that borrows
+        /// `raw_pixels` mutably (e.g. an in-place effect or filter) must call
+        /// this afterwards so a stale cache isn't served by `as_data_url`.
+        pub(crate) fn invalidate_data_url_cache(&self) {
+            *self.data_url_cache.borrow_mut() = None;
+        }
+
+        /// Mutably borrow `raw_pixels`, invalidating the cached `data:` URL since
+        /// the caller may write through it. Every in-crate mutator (effects,
+        /// filters, `set_imgdata`, etc.) must obtain its mutable access to the
+        /// pixel buffer through this method, since the field itself is private
+        /// to this module and unreachable from elsewhere in the crate.
+        pub(crate) fn raw_pixels_mut(&mut self) -> &mut Vec<u8> {
+            self.invalidate_data_url_cache();
+            &mut self.raw_pixels
+        }
+
+        /// Borrow `raw_pixels` read-only. For in-crate code (e.g. `transform`,
+        /// `multiple`) that needs to read pixels without cloning via
+        /// `get_raw_pixels` and without touching the `data:` URL cache.
+        pub(crate) fn raw_pixels(&self) -> &[u8] {
+            &self.raw_pixels
+        }
+
+        /// Consume the PhotonImage, returning its raw pixels, width and height.
+        pub(crate) fn into_raw_pixels(self) -> (Vec<u8>, u32, u32) {
+            (self.raw_pixels, self.width, self.height)
+        }
     }
 
-    /// Convert ImageData to raw pixels, and update the PhotonImage's raw pixels to this.
-    pub fn set_imgdata(&mut self, img_data: ImageData) {
-        let width = img_data.width();
-        let height = img_data.height();
-        let raw_pixels = to_raw_pixels(img_data);
-        self.width = width;
-        self.height = height;
-        self.raw_pixels = raw_pixels;
+    /// Create a new PhotonImage from a raw Vec of u8s representing raw image pixels.
+    impl From<ImageData> for PhotonImage {
+        fn from(imgdata: ImageData) -> Self {
+            let width = imgdata.width();
+            let height = imgdata.height();
+            let raw_pixels = to_raw_pixels(imgdata);
+            return PhotonImage {raw_pixels: raw_pixels, width: width, height: height, data_url_cache: RefCell::new(None)}
+        }
     }
 }
+pub use photon_image::PhotonImage;
 
-/// Create a new PhotonImage from a raw Vec of u8s representing raw image pixels.
-impl From<ImageData> for PhotonImage {
-    fn from(imgdata: ImageData) -> Self {
-        let width = imgdata.width();
-        let height = imgdata.height();
-        let raw_pixels = to_raw_pixels(imgdata);
-        return PhotonImage {raw_pixels: raw_pixels, width: width, height: height}
-    }
+/// Encoded output formats supported by [`PhotonImage::to_bytes`].
+///
+/// WebP is intentionally not listed: the underlying `image` crate version
+/// this encoder targets has no WebP encoder, and this crate doesn't expose
+/// formats it can't actually produce.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
 }
 
 /// RGB color type.
@@ -231,16 +414,17 @@ pub fn get_image_data(canvas: &HtmlCanvasElement, ctx: &CanvasRenderingContext2d
 
 /// Place a PhotonImage onto a 2D canvas.
 #[wasm_bindgen]
-pub fn putImageData(canvas: HtmlCanvasElement, ctx: CanvasRenderingContext2d, mut new_image: PhotonImage) {
+pub fn putImageData(canvas: HtmlCanvasElement, ctx: CanvasRenderingContext2d, new_image: PhotonImage) {
     // Convert the raw pixels back to an ImageData object.
-    let new_img_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut new_image.raw_pixels), canvas.width(), canvas.height());
+    let mut raw_pixels = new_image.get_raw_pixels();
+    let new_img_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut raw_pixels), canvas.width(), canvas.height());
 
     // Place the new imagedata onto the canvas
     ctx.put_image_data(&new_img_data.unwrap(), 0.0, 0.0);
 }
 
 /// Convert a HTML5 Canvas Element to a PhotonImage.
-/// 
+///
 /// This converts the ImageData found in the canvas context to a PhotonImage,
 /// which can then have effects or filters applied to it.
 #[wasm_bindgen]
@@ -248,7 +432,7 @@ pub fn putImageData(canvas: HtmlCanvasElement, ctx: CanvasRenderingContext2d, mu
 pub fn open_image(canvas: HtmlCanvasElement, ctx: CanvasRenderingContext2d) -> PhotonImage {
     let imgdata = get_image_data(&canvas, &ctx);
     let raw_pixels = to_raw_pixels(imgdata);
-    return PhotonImage {raw_pixels: raw_pixels, width: canvas.width(), height: canvas.height() }
+    PhotonImage::new(raw_pixels, canvas.width(), canvas.height())
 }
 
 
@@ -261,36 +445,31 @@ pub fn to_raw_pixels(imgdata: ImageData) -> Vec<u8> {
 
 /// Convert a base64 string to a PhotonImage.
 #[wasm_bindgen]
-pub fn base64_to_image(base64: &str) -> PhotonImage {
-
-    let base64_to_vec: Vec<u8> = base64_to_vec(base64);
+pub fn base64_to_image(base64: &str) -> Result<PhotonImage, PhotonError> {
+    let base64_to_vec: Vec<u8> = base64_to_vec(base64)?;
 
     let slice = base64_to_vec.as_slice();
 
-    let img = image::load_from_memory(slice).unwrap();
-    
+    let img = image::load_from_memory(slice)?;
+
     let raw_pixels = img.raw_pixels();
-    
-    return PhotonImage { raw_pixels: raw_pixels, width: img.width(), height: img.height()};
 
+    Ok(PhotonImage::new(raw_pixels, img.width(), img.height()))
 }
 
 /// Convert a base64 string to a Vec of u8s.
 #[wasm_bindgen]
-pub fn base64_to_vec(base64: &str) -> Vec<u8> {
-    let vec = decode(base64).unwrap();
-    return vec;
+pub fn base64_to_vec(base64: &str) -> Result<Vec<u8>, PhotonError> {
+    let vec = decode(base64)?;
+    Ok(vec)
 }
 
 /// Convert a PhotonImage to JS-compatible ImageData.
 #[wasm_bindgen]
-pub fn to_image_data(photon_image: PhotonImage) -> ImageData {
-    let mut raw_pixels = photon_image.raw_pixels;
-    let width = photon_image.width;
-    let height = photon_image.height;
-    let new_img_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut raw_pixels), width, height).unwrap();
-
-    return new_img_data;
+pub fn to_image_data(photon_image: PhotonImage) -> Result<ImageData, PhotonError> {
+    let (mut raw_pixels, width, height) = photon_image.into_raw_pixels();
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut raw_pixels), width, height)
+        .map_err(|err| PhotonError::InvalidDimensions(format!("{:?}", err)))
 }
 
 fn set_panic_hook() {
@@ -311,5 +490,6 @@ pub mod text;
 pub mod colour_spaces;
 pub mod multiple;
 pub mod noise;
+pub mod identicon;
 pub mod helpers;
 mod tests;
\ No newline at end of file
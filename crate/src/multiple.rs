@@ -0,0 +1,146 @@
+//! Layered composition: build up an image from multiple positioned layers
+//! with per-layer opacity and blend mode, then flatten them into a single
+//! PhotonImage in one pass &mdash; a draw-order model analogous to a canvas's
+//! layer stack.
+
+use wasm_bindgen::prelude::*;
+
+use crate::PhotonImage;
+
+/// Per-layer compositing mode. This is the crate's single shared blend
+/// implementation: any future pairwise blend helper should call
+/// [`BlendMode::blend_channel`] rather than reimplementing the per-channel
+/// formulas again.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+}
+
+impl BlendMode {
+    /// Blend a single colour channel pair (each in `0.0-1.0`) according to
+    /// this mode. Takes/returns un-premultiplied, normalized values so
+    /// callers can weight the result by alpha themselves.
+    pub(crate) fn blend_channel(&self, base: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Multiply => base * top,
+            BlendMode::Screen => 1.0 - (1.0 - base) * (1.0 - top),
+            BlendMode::Overlay => {
+                if base < 0.5 {
+                    2.0 * base * top
+                } else {
+                    1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+                }
+            }
+            BlendMode::Darken => base.min(top),
+            BlendMode::Lighten => base.max(top),
+        }
+    }
+}
+
+/// A single positioned, semi-transparent layer on a `Canvas`.
+struct Layer {
+    image: PhotonImage,
+    x: i32,
+    y: i32,
+    opacity: f32,
+    blend_mode: BlendMode,
+}
+
+/// Composites an ordered stack of image layers onto a base image. Layers are
+/// added back-to-front with `add_layer` and composited in a single pass with
+/// `flatten`, which lets badges, framed overlays, and collages be built
+/// without chaining pairwise blend calls.
+#[wasm_bindgen]
+pub struct Canvas {
+    base: PhotonImage,
+    layers: Vec<Layer>,
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    #[wasm_bindgen(constructor)]
+    pub fn new(base: PhotonImage) -> Canvas {
+        Canvas { base, layers: Vec::new() }
+    }
+
+    /// Stack `image` onto the canvas at `(x, y)`, with per-layer `opacity`
+    /// (`0.0`-`1.0`) and `blend_mode`. Layers are composited in the order
+    /// they were added.
+    pub fn add_layer(&mut self, image: PhotonImage, x: i32, y: i32, opacity: f32, blend_mode: BlendMode) {
+        self.layers.push(Layer { image, x, y, opacity: opacity.max(0.0).min(1.0), blend_mode });
+    }
+
+    /// Alpha-composite every layer onto the base image, clipping each layer
+    /// to the base's bounds, and return the resulting flattened PhotonImage.
+    pub fn flatten(&self) -> PhotonImage {
+        let width = self.base.get_width();
+        let height = self.base.get_height();
+        let mut raw_pixels = self.base.raw_pixels().to_vec();
+
+        for layer in &self.layers {
+            composite_layer(&mut raw_pixels, width, height, layer);
+        }
+
+        PhotonImage::new(raw_pixels, width, height)
+    }
+}
+
+/// Alpha-composite a single layer onto `raw_pixels`, clipping to bounds.
+///
+/// Follows the standard "simple alpha compositing" model: the blend mode
+/// only gets to influence the result in proportion to how opaque the
+/// backdrop actually is, and the blended-then-composited colour is
+/// un-premultiplied by the output alpha at the end. Blending straight
+/// against `raw_pixels`' RGB unconditionally (as if the backdrop were
+/// always opaque) pulls the result toward that RGB's garbage value when the
+/// backdrop is actually transparent, producing dark fringes.
+fn composite_layer(raw_pixels: &mut [u8], width: u32, height: u32, layer: &Layer) {
+    let layer_width = layer.image.get_width();
+    let layer_height = layer.image.get_height();
+    let layer_pixels = layer.image.raw_pixels();
+
+    for ly in 0..layer_height {
+        let dest_y = layer.y + ly as i32;
+        if dest_y < 0 || dest_y as u32 >= height {
+            continue;
+        }
+
+        for lx in 0..layer_width {
+            let dest_x = layer.x + lx as i32;
+            if dest_x < 0 || dest_x as u32 >= width {
+                continue;
+            }
+
+            let src_index = ((ly * layer_width + lx) * 4) as usize;
+            let dest_index = ((dest_y as u32 * width + dest_x as u32) * 4) as usize;
+
+            let src_alpha = (layer_pixels[src_index + 3] as f32 / 255.0) * layer.opacity;
+            if src_alpha <= 0.0 {
+                continue;
+            }
+
+            let dest_alpha = raw_pixels[dest_index + 3] as f32 / 255.0;
+            let out_alpha = src_alpha + dest_alpha * (1.0 - src_alpha);
+
+            for channel in 0..3 {
+                let base_colour = raw_pixels[dest_index + channel] as f32 / 255.0;
+                let top_colour = layer_pixels[src_index + channel] as f32 / 255.0;
+                let blended = layer.blend_mode.blend_channel(base_colour, top_colour);
+
+                let source_colour = dest_alpha * blended + (1.0 - dest_alpha) * top_colour;
+                let composited_premul = src_alpha * source_colour + (1.0 - src_alpha) * dest_alpha * base_colour;
+                let composited = if out_alpha > 0.0 { composited_premul / out_alpha } else { 0.0 };
+                raw_pixels[dest_index + channel] = (composited.max(0.0).min(1.0) * 255.0).round() as u8;
+            }
+
+            raw_pixels[dest_index + 3] = (out_alpha.max(0.0).min(1.0) * 255.0).round() as u8;
+        }
+    }
+}
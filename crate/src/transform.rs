@@ -0,0 +1,94 @@
+//! Image transformations: resizing, cropping, and ready-made templates for
+//! common social media asset dimensions.
+
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Rgba};
+use wasm_bindgen::prelude::*;
+
+use crate::{PhotonError, PhotonImage, Rgb};
+
+/// Named presets for common social media image dimensions.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Template {
+    InstagramSquare,
+    InstagramStory,
+    TwitterHeader,
+    FacebookCover,
+    YoutubeThumbnail,
+}
+
+impl Template {
+    /// The target `(width, height)` in pixels for this template.
+    fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Template::InstagramSquare => (1080, 1080),
+            Template::InstagramStory => (1080, 1920),
+            Template::TwitterHeader => (1500, 500),
+            Template::FacebookCover => (820, 312),
+            Template::YoutubeThumbnail => (1280, 720),
+        }
+    }
+}
+
+/// How a source image should be fit into a template's target box.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fit {
+    /// Scale to fully cover the target box, then center-crop the overflow.
+    Cover,
+    /// Scale to fully fit inside the target box, then pad with a background colour.
+    Contain,
+    /// Scale width and height independently to exactly match the target box.
+    Stretch,
+}
+
+/// Resize (and crop or pad, depending on `fit`) a PhotonImage to a named
+/// social media template.
+#[wasm_bindgen]
+pub fn resize_to_template(img: &PhotonImage, template: Template, fit: Fit, background: &Rgb) -> Result<PhotonImage, PhotonError> {
+    let (target_width, target_height) = template.dimensions();
+    resize_to_fit(img, target_width, target_height, fit, background)
+}
+
+/// Resize (and crop or pad, depending on `fit`) a PhotonImage to an arbitrary
+/// target size.
+#[wasm_bindgen]
+pub fn resize_to_fit(img: &PhotonImage, target_width: u32, target_height: u32, fit: Fit, background: &Rgb) -> Result<PhotonImage, PhotonError> {
+    let width = img.get_width();
+    let height = img.get_height();
+    let src: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_raw(width, height, img.raw_pixels().to_vec())
+        .ok_or_else(|| PhotonError::InvalidDimensions("raw pixels do not match width/height".to_string()))?;
+
+    let resized = match fit {
+        Fit::Stretch => imageops::resize(&src, target_width, target_height, FilterType::Lanczos3),
+        Fit::Cover => {
+            let scale = (target_width as f64 / width as f64).max(target_height as f64 / height as f64);
+            let scaled_width = (width as f64 * scale).round() as u32;
+            let scaled_height = (height as f64 * scale).round() as u32;
+            let mut scaled = imageops::resize(&src, scaled_width, scaled_height, FilterType::Lanczos3);
+
+            let crop_x = scaled_width.saturating_sub(target_width) / 2;
+            let crop_y = scaled_height.saturating_sub(target_height) / 2;
+            imageops::crop(&mut scaled, crop_x, crop_y, target_width, target_height).to_image()
+        }
+        Fit::Contain => {
+            let scale = (target_width as f64 / width as f64).min(target_height as f64 / height as f64);
+            let scaled_width = (width as f64 * scale).round() as u32;
+            let scaled_height = (height as f64 * scale).round() as u32;
+            let scaled = imageops::resize(&src, scaled_width, scaled_height, FilterType::Lanczos3);
+
+            let mut canvas: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_pixel(
+                target_width,
+                target_height,
+                Rgba([background.r, background.g, background.b, 255]),
+            );
+            let paste_x = target_width.saturating_sub(scaled_width) / 2;
+            let paste_y = target_height.saturating_sub(scaled_height) / 2;
+            imageops::overlay(&mut canvas, &scaled, paste_x, paste_y);
+            canvas
+        }
+    };
+
+    Ok(PhotonImage::new(resized.into_raw(), target_width, target_height))
+}